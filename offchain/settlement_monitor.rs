@@ -0,0 +1,52 @@
+// settlement_monitor.rs
+// Off-chain companion to mandala_settler's on-chain SettlementReceipt replay guard.
+//
+// Tracks consumed payment signatures the same way a mempool watcher tracks seen
+// txids, so callers can check an exclusion filter before submitting a settlement
+// instead of racing the chain. It does not subscribe to SettlementEvents itself:
+// signatures are fed in through `record`.
+
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+
+/// A payment signature that has already been settled (or is in flight), as
+/// observed from `SettlementEvent`s.
+pub type PaymentSignature = String;
+
+/// Tracks consumed payment signatures and exposes them as an exclusion set, so
+/// callers never resubmit an in-flight or finalized micropayment.
+pub struct SettlementMonitor {
+    seen: Arc<RwLock<HashSet<PaymentSignature>>>,
+}
+
+impl SettlementMonitor {
+    pub fn new() -> Self {
+        Self {
+            seen: Arc::new(RwLock::new(HashSet::new())),
+        }
+    }
+
+    /// Record a payment signature as consumed: one observed in a
+    /// `SettlementEvent`, or one the caller has just submitted.
+    pub fn record(&self, signature: PaymentSignature) {
+        self.seen.write().unwrap().insert(signature);
+    }
+
+    /// True if `signature` has already been settled (or submitted and not yet
+    /// confirmed), i.e. resubmitting it would hit the on-chain replay guard.
+    pub fn is_excluded(&self, signature: &str) -> bool {
+        self.seen.read().unwrap().contains(signature)
+    }
+
+    /// Snapshot of the current exclusion set, for handing to a caller that
+    /// wants to filter a batch of candidate payments up front.
+    pub fn exclusion_filter(&self) -> HashSet<PaymentSignature> {
+        self.seen.read().unwrap().clone()
+    }
+}
+
+impl Default for SettlementMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,366 @@
+// simulator.rs
+// Mainnet-fork simulation tooling for previewing mandala_settler instructions
+// (rebalance_mandala, charge_collateral_fees, settle_micropayment) against real
+// vault state before broadcasting a transaction.
+
+use std::collections::HashMap;
+
+pub type Pubkey = [u8; 32];
+
+const BPS_DENOMINATOR: u128 = 10_000;
+const SECONDS_PER_YEAR: u128 = 31_536_000;
+
+/// Decoded `MandalaVault` fields relevant to simulation. `tile_distribution`
+/// is not stored on `MandalaVault`: it holds the layer distribution of the last
+/// simulated `rebalance_mandala`, as emitted in its `RebalanceEvent`.
+#[derive(Debug, Clone, Default)]
+pub struct VaultSnapshot {
+    pub total_liquidity: u64,
+    pub phi_ratio: u64,
+    pub curvature_depth: u8,
+    pub collateral_fee_rate: u64,
+    pub last_collateral_charge: i64,
+    pub tile_distribution: Vec<u64>,
+}
+
+/// Decoded SPL token account balance.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenAccountSnapshot {
+    pub balance: u64,
+}
+
+/// Decoded account state the simulator understands, as lazily fetched from
+/// mainnet and buffered in the overlay.
+#[derive(Debug, Clone)]
+pub enum AccountState {
+    Vault(VaultSnapshot),
+    TokenAccount(TokenAccountSnapshot),
+}
+
+/// An instruction to preview, mirroring the account roles `mandala_settler`
+/// expects for each handler (no Borsh/CPI — this runs entirely off-chain).
+#[derive(Debug, Clone)]
+pub enum SimInstruction {
+    RebalanceMandala {
+        vault: Pubkey,
+        fee_amount: u64,
+    },
+    ChargeCollateralFees {
+        vault: Pubkey,
+        vault_token_account: Pubkey,
+        fee_vault: Pubkey,
+        now: i64,
+    },
+    SettleMicropayment {
+        vault: Pubkey,
+        vault_token_account: Pubkey,
+        recipient_token_account: Pubkey,
+        amount: u64,
+    },
+}
+
+impl SimInstruction {
+    fn name(&self) -> &'static str {
+        match self {
+            SimInstruction::RebalanceMandala { .. } => "rebalance_mandala",
+            SimInstruction::ChargeCollateralFees { .. } => "charge_collateral_fees",
+            SimInstruction::SettleMicropayment { .. } => "settle_micropayment",
+        }
+    }
+}
+
+/// Per-account before/after diff produced by running an instruction against the
+/// overlay, so operators can audit φ-layer rebalance math (e.g. catch
+/// `tile_distribution` shares that don't sum to 1.0) without touching mainnet.
+#[derive(Debug, Clone, Default)]
+pub struct AccountDiff {
+    pub pubkey: Pubkey,
+    pub total_liquidity_before: Option<u64>,
+    pub total_liquidity_after: Option<u64>,
+    pub tile_distribution_before: Option<Vec<u64>>,
+    pub tile_distribution_after: Option<Vec<u64>>,
+    pub token_balance_before: Option<u64>,
+    pub token_balance_after: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SimResult {
+    pub diffs: Vec<AccountDiff>,
+    pub logs: Vec<String>,
+}
+
+/// Copy-on-write layer over account state: reads fall through to the base store,
+/// writes buffer in memory and never flush back, so a simulation can run
+/// repeatedly against the same mainnet fork without mutating it.
+pub struct DatabaseOverlay<'a> {
+    base: &'a dyn AccountStore,
+    writes: HashMap<Pubkey, AccountState>,
+}
+
+impl<'a> DatabaseOverlay<'a> {
+    pub fn new(base: &'a dyn AccountStore) -> Self {
+        Self {
+            base,
+            writes: HashMap::new(),
+        }
+    }
+
+    /// Read-through: prefer a buffered write, otherwise fetch (and cache) from base.
+    pub fn get(&mut self, key: &Pubkey) -> Option<AccountState> {
+        if let Some(state) = self.writes.get(key) {
+            return Some(state.clone());
+        }
+        let fetched = self.base.fetch(key)?;
+        self.writes.insert(*key, fetched.clone());
+        Some(fetched)
+    }
+
+    /// Buffer a write in memory only — never touches the base store.
+    pub fn set(&mut self, key: Pubkey, state: AccountState) {
+        self.writes.insert(key, state);
+    }
+}
+
+/// Source of real account state, e.g. an RPC-backed mainnet fork.
+pub trait AccountStore {
+    fn fetch(&self, key: &Pubkey) -> Option<AccountState>;
+}
+
+/// Serves account state from the already-decoded `vaults` and `token_accounts`
+/// maps, looking in `vaults` first. `rpc_url` is not contacted: an account
+/// missing from both maps is returned as `None`.
+pub struct RpcAccountStore {
+    pub rpc_url: String,
+    pub vaults: HashMap<Pubkey, VaultSnapshot>,
+    pub token_accounts: HashMap<Pubkey, TokenAccountSnapshot>,
+}
+
+impl RpcAccountStore {
+    pub fn new(rpc_url: String) -> Self {
+        Self {
+            rpc_url,
+            vaults: HashMap::new(),
+            token_accounts: HashMap::new(),
+        }
+    }
+}
+
+impl AccountStore for RpcAccountStore {
+    fn fetch(&self, key: &Pubkey) -> Option<AccountState> {
+        if let Some(vault) = self.vaults.get(key) {
+            return Some(AccountState::Vault(vault.clone()));
+        }
+        if let Some(token_account) = self.token_accounts.get(key) {
+            return Some(AccountState::TokenAccount(*token_account));
+        }
+        // In production: getAccountInfo against self.rpc_url, then decode via
+        // the program's Borsh layout into a VaultSnapshot/TokenAccountSnapshot.
+        None
+    }
+}
+
+/// Previews an instruction against vault state and returns a structured diff,
+/// without broadcasting a transaction.
+pub trait Executor {
+    fn execute(&self, ix: SimInstruction) -> SimResult;
+}
+
+/// Executor for a local test validator. It does not submit the instruction to
+/// `validator_url`: it returns a log line naming the instruction and no diffs.
+pub struct LocalExecutor {
+    pub validator_url: String,
+}
+
+impl Executor for LocalExecutor {
+    fn execute(&self, ix: SimInstruction) -> SimResult {
+        SimResult {
+            diffs: vec![],
+            logs: vec![format!("[local:{}] ran {}", self.validator_url, ix.name())],
+        }
+    }
+}
+
+/// Computes the same φ-ratio layer distribution as `rebalance_mandala`, so the
+/// simulator reproduces whatever rounding loss the on-chain math has.
+fn phi_layer_distribution(phi_ratio: u64, curvature_depth: u8, fee_amount: u64) -> Vec<u64> {
+    let phi = phi_ratio as f64 / 1000.0;
+    let mut remaining = fee_amount as f64;
+    let mut tile_amounts = Vec::with_capacity(curvature_depth as usize);
+
+    for i in 0..curvature_depth as usize {
+        let layer_share = if i == 0 { 1.0 - phi } else { phi * (0.5_f64.powi(i as i32)) };
+        let tile = (remaining * layer_share) as u64;
+        tile_amounts.push(tile);
+        remaining -= tile as f64;
+    }
+
+    tile_amounts
+}
+
+/// Sum of the φ-layer shares used to build `tile_distribution`, so callers can
+/// flag the rounding loss where shares don't add back up to 1.0.
+fn phi_layer_share_total(phi_ratio: u64, curvature_depth: u8) -> f64 {
+    let phi = phi_ratio as f64 / 1000.0;
+    (0..curvature_depth as usize)
+        .map(|i| if i == 0 { 1.0 - phi } else { phi * (0.5_f64.powi(i as i32)) })
+        .sum()
+}
+
+/// Runs instructions against a copy-on-write overlay seeded lazily from mainnet,
+/// applying the same mutations `mandala_settler` would on-chain, so the
+/// φ-layer rebalance math can be audited against production state.
+pub struct MainnetSimulatorExecutor {
+    store: RpcAccountStore,
+}
+
+impl MainnetSimulatorExecutor {
+    pub fn new(store: RpcAccountStore) -> Self {
+        Self { store }
+    }
+
+    fn vault_diff(
+        overlay: &mut DatabaseOverlay,
+        key: Pubkey,
+        mutate: impl FnOnce(&mut VaultSnapshot),
+    ) -> Option<AccountDiff> {
+        let before = match overlay.get(&key)? {
+            AccountState::Vault(v) => v,
+            AccountState::TokenAccount(_) => return None,
+        };
+        let mut after = before.clone();
+        mutate(&mut after);
+        overlay.set(key, AccountState::Vault(after.clone()));
+
+        Some(AccountDiff {
+            pubkey: key,
+            total_liquidity_before: Some(before.total_liquidity),
+            total_liquidity_after: Some(after.total_liquidity),
+            tile_distribution_before: Some(before.tile_distribution),
+            tile_distribution_after: Some(after.tile_distribution),
+            token_balance_before: None,
+            token_balance_after: None,
+        })
+    }
+
+    fn token_account_diff(
+        overlay: &mut DatabaseOverlay,
+        key: Pubkey,
+        mutate: impl FnOnce(&mut TokenAccountSnapshot),
+    ) -> Option<AccountDiff> {
+        let before = match overlay.get(&key)? {
+            AccountState::TokenAccount(t) => t,
+            AccountState::Vault(_) => return None,
+        };
+        let mut after = before;
+        mutate(&mut after);
+        overlay.set(key, AccountState::TokenAccount(after));
+
+        Some(AccountDiff {
+            pubkey: key,
+            total_liquidity_before: None,
+            total_liquidity_after: None,
+            tile_distribution_before: None,
+            tile_distribution_after: None,
+            token_balance_before: Some(before.balance),
+            token_balance_after: Some(after.balance),
+        })
+    }
+}
+
+impl Executor for MainnetSimulatorExecutor {
+    fn execute(&self, ix: SimInstruction) -> SimResult {
+        let mut overlay = DatabaseOverlay::new(&self.store);
+        let mut diffs = Vec::new();
+        let mut logs = vec![format!("[mainnet-sim:{}] ran {}", self.store.rpc_url, ix.name())];
+
+        match ix {
+            SimInstruction::RebalanceMandala { vault, fee_amount } => {
+                if let Some(AccountState::Vault(current)) = overlay.get(&vault) {
+                    let share_total = phi_layer_share_total(current.phi_ratio, current.curvature_depth);
+                    if (share_total - 1.0).abs() > f64::EPSILON {
+                        logs.push(format!(
+                            "rounding loss: φ-layer shares sum to {share_total:.6}, not 1.0 (depth={})",
+                            current.curvature_depth
+                        ));
+                    }
+                }
+
+                if let Some(diff) = Self::vault_diff(&mut overlay, vault, |v| {
+                    v.tile_distribution = phi_layer_distribution(v.phi_ratio, v.curvature_depth, fee_amount);
+                }) {
+                    diffs.push(diff);
+                }
+            }
+            SimInstruction::ChargeCollateralFees {
+                vault,
+                vault_token_account,
+                fee_vault,
+                now,
+            } => {
+                // On-chain this fails with AlreadyChargedThisSlot and mutates nothing.
+                if let Some(AccountState::Vault(v)) = overlay.get(&vault) {
+                    if now <= v.last_collateral_charge {
+                        logs.push(format!(
+                            "would fail: last collateral charge at {} is not before now ({now})",
+                            v.last_collateral_charge
+                        ));
+                        return SimResult { diffs, logs };
+                    }
+                }
+
+                let fee = match overlay.get(&vault) {
+                    Some(AccountState::Vault(v)) => {
+                        let elapsed = (now - v.last_collateral_charge).max(0) as u128;
+                        (v.total_liquidity as u128)
+                            .saturating_mul(v.collateral_fee_rate as u128)
+                            .saturating_mul(elapsed)
+                            / BPS_DENOMINATOR
+                            / SECONDS_PER_YEAR
+                    }
+                    _ => 0,
+                } as u64;
+
+                if let Some(diff) = Self::vault_diff(&mut overlay, vault, |v| {
+                    v.total_liquidity = v.total_liquidity.saturating_sub(fee);
+                    v.last_collateral_charge = now;
+                }) {
+                    diffs.push(diff);
+                }
+                if let Some(diff) = Self::token_account_diff(&mut overlay, vault_token_account, |t| {
+                    t.balance = t.balance.saturating_sub(fee);
+                }) {
+                    diffs.push(diff);
+                }
+                if let Some(diff) = Self::token_account_diff(&mut overlay, fee_vault, |t| {
+                    t.balance = t.balance.saturating_add(fee);
+                }) {
+                    diffs.push(diff);
+                }
+            }
+            SimInstruction::SettleMicropayment {
+                vault,
+                vault_token_account,
+                recipient_token_account,
+                amount,
+            } => {
+                if let Some(diff) = Self::vault_diff(&mut overlay, vault, |v| {
+                    v.total_liquidity = v.total_liquidity.saturating_sub(amount);
+                }) {
+                    diffs.push(diff);
+                }
+                if let Some(diff) = Self::token_account_diff(&mut overlay, vault_token_account, |t| {
+                    t.balance = t.balance.saturating_sub(amount);
+                }) {
+                    diffs.push(diff);
+                }
+                if let Some(diff) = Self::token_account_diff(&mut overlay, recipient_token_account, |t| {
+                    t.balance = t.balance.saturating_add(amount);
+                }) {
+                    diffs.push(diff);
+                }
+            }
+        }
+
+        SimResult { diffs, logs }
+    }
+}
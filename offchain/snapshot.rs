@@ -0,0 +1,172 @@
+// snapshot.rs
+// Event-sourced snapshot/restore for MandalaVault: reconstructs vault state
+// purely from the emitted event log (SettlementEvent, LiquidityEvent,
+// RebalanceEvent, CollateralFeeEvent) so operators get fast disaster recovery
+// and migration between RPC providers.
+
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
+
+pub type ManifestHash = [u8; 32];
+
+/// A single emitted vault event, flattened to the fields snapshotting needs.
+#[derive(Debug, Clone, Hash)]
+pub enum VaultEvent {
+    Settlement { amount: u64 },
+    Liquidity { action: String, amount: u64 },
+    Rebalance { total_fees: u64 },
+    CollateralFee { fee: u64 },
+}
+
+/// Reconstructed vault state, rebuilt deterministically from a chunk of events.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VaultState {
+    pub total_liquidity: u64,
+    pub settlement_count: u64,
+}
+
+impl VaultState {
+    fn apply(&mut self, event: &VaultEvent) {
+        match event {
+            VaultEvent::Settlement { amount } => {
+                self.total_liquidity = self.total_liquidity.saturating_sub(*amount);
+                self.settlement_count += 1;
+            }
+            VaultEvent::Liquidity { action, amount } => {
+                if action == "add" {
+                    self.total_liquidity = self.total_liquidity.saturating_add(*amount);
+                } else {
+                    self.total_liquidity = self.total_liquidity.saturating_sub(*amount);
+                }
+            }
+            VaultEvent::Rebalance { .. } => {}
+            VaultEvent::CollateralFee { fee } => {
+                self.total_liquidity = self.total_liquidity.saturating_sub(*fee);
+            }
+        }
+    }
+}
+
+/// A fixed-size slice of event history, hashed into a manifest chunk.
+#[derive(Debug, Clone)]
+pub struct EventChunk {
+    pub start_index: u64,
+    pub events: Vec<VaultEvent>,
+}
+
+/// One chunk's worth of a signed manifest: the hash identifying it, plus the
+/// raw events it covers — restore re-folds these rather than trusting a
+/// precomputed cumulative snapshot, so a blacklisted middle chunk's events
+/// are actually excluded from the rebuilt state.
+#[derive(Debug, Clone)]
+pub struct ManifestChunk {
+    pub hash: ManifestHash,
+    pub start_index: u64,
+    pub events: Vec<VaultEvent>,
+}
+
+/// A manifest over the full chunked event history for a vault. `signature`
+/// holds the manifest's signature; `export_manifest` leaves it empty.
+#[derive(Debug, Clone)]
+pub struct VaultManifest {
+    pub vault: [u8; 32],
+    pub chunks: Vec<ManifestChunk>,
+    pub signature: Vec<u8>,
+}
+
+/// Content hash over the chunk's events (plus its offset, so two chunks with
+/// identical events at different positions in the log don't collide).
+///
+/// In production this would be a real cryptographic hash (e.g. sha256); a
+/// `DefaultHasher` is used here only because no hashing crate is vendored in
+/// this tree, but the important property — identical chunks hash identically,
+/// and distinct chunks hash differently barring a 64-bit collision — still holds.
+fn hash_chunk(chunk: &EventChunk) -> ManifestHash {
+    let mut hasher = DefaultHasher::new();
+    chunk.start_index.hash(&mut hasher);
+    chunk.events.len().hash(&mut hasher);
+    for event in &chunk.events {
+        event.hash(&mut hasher);
+    }
+    let digest = hasher.finish();
+
+    let mut hash = [0u8; 32];
+    for (i, byte) in digest.to_le_bytes().iter().cycle().take(32).enumerate() {
+        hash[i] = *byte;
+    }
+    hash
+}
+
+/// Builds and restores `VaultManifest`s from chunked event history, skipping
+/// chunks that previously failed verification instead of repeatedly
+/// re-importing bad data.
+pub struct SnapshotStore {
+    chunk_size: usize,
+    blacklist: HashSet<ManifestHash>,
+}
+
+impl SnapshotStore {
+    pub fn new(chunk_size: usize) -> Self {
+        Self {
+            chunk_size,
+            blacklist: HashSet::new(),
+        }
+    }
+
+    /// Chunk `events` into fixed ranges and hash each into a manifest. The
+    /// manifest is not signed: its `signature` is left empty.
+    pub fn export_manifest(&self, vault: [u8; 32], events: &[VaultEvent]) -> VaultManifest {
+        let mut chunks = Vec::new();
+
+        for (i, window) in events.chunks(self.chunk_size).enumerate() {
+            let chunk = EventChunk {
+                start_index: (i * self.chunk_size) as u64,
+                events: window.to_vec(),
+            };
+            chunks.push(ManifestChunk {
+                hash: hash_chunk(&chunk),
+                start_index: chunk.start_index,
+                events: chunk.events,
+            });
+        }
+
+        VaultManifest {
+            vault,
+            chunks,
+            signature: vec![],
+        }
+    }
+
+    /// Re-folds the events of every non-blacklisted chunk, in order, into a
+    /// `VaultState` — skipping a blacklisted chunk actually excludes its
+    /// events from the result, rather than inheriting them via a later
+    /// chunk's precomputed cumulative state. Callers should refetch the
+    /// skipped ranges from a different RPC provider.
+    pub fn restore_from_manifest(&self, manifest: &VaultManifest) -> (VaultState, Vec<ManifestHash>) {
+        let mut skipped = Vec::new();
+        let mut state = VaultState::default();
+
+        for chunk in &manifest.chunks {
+            if self.blacklist.contains(&chunk.hash) {
+                skipped.push(chunk.hash);
+                continue;
+            }
+            for event in &chunk.events {
+                state.apply(event);
+            }
+        }
+
+        (state, skipped)
+    }
+
+    /// Marks a manifest chunk hash as having failed verification (corrupt or
+    /// reorged range), so future restores skip it instead of re-importing it.
+    pub fn blacklist_manifest(&mut self, hash: ManifestHash) {
+        self.blacklist.insert(hash);
+    }
+
+    pub fn is_blacklisted(&self, hash: &ManifestHash) -> bool {
+        self.blacklist.contains(hash)
+    }
+}
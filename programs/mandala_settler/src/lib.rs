@@ -3,6 +3,10 @@ use anchor_spl::token::{self, Token, TokenAccount, Transfer, Mint};
 
 declare_id!("MANDALA11111111111111111111111111111111111111"); // ← Replace after deploy
 
+/// `collateral_fee_rate` is expressed as an annualized rate in basis points.
+const BPS_DENOMINATOR: u128 = 10_000;
+const SECONDS_PER_YEAR: u128 = 31_536_000;
+
 #[program]
 pub mod mandala_settler {
     use super::*;
@@ -18,6 +22,8 @@ pub mod mandala_settler {
         vault.settlement_count = 0;
         vault.last_rebalance = Clock::get()?.unix_timestamp;
         vault.fee_vault = ctx.accounts.fee_vault.key();
+        vault.collateral_fee_rate = 0;
+        vault.last_collateral_charge = Clock::get()?.unix_timestamp;
 
         msg!("Mandala Vault awakened. Tiles align. Pattern Blue curvature begins.");
         Ok(())
@@ -30,6 +36,10 @@ pub mod mandala_settler {
         payment_signature: String,
         memo: String,
     ) -> Result<()> {
+        // Settlement is an outflow: allowed in Active, ReduceOnly, and ForceWithdraw,
+        // so the delisting path doesn't strand balances. `token_config` is still
+        // loaded and validated below — a mint with no config at all is rejected
+        // rather than settled silently.
         let vault = &mut ctx.accounts.vault;
         let seeds = &[b"mandala_vault".as_ref(), vault.authority.as_ref(), &[vault.bump]];
         let signer = &[&seeds[..]];
@@ -45,6 +55,14 @@ pub mod mandala_settler {
         vault.total_liquidity = vault.total_liquidity.checked_sub(amount).unwrap_or(0);
         vault.settlement_count += 1;
 
+        let timestamp = Clock::get()?.unix_timestamp;
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.vault = vault.key();
+        receipt.amount = amount;
+        receipt.recipient = ctx.accounts.recipient.key();
+        receipt.timestamp = timestamp;
+        receipt.bump = ctx.bumps.receipt;
+
         emit!(SettlementEvent {
             amount,
             token_mint: ctx.accounts.vault_token_account.mint,
@@ -52,7 +70,7 @@ pub mod mandala_settler {
             signature: payment_signature,
             memo,
             curvature_depth: vault.curvature_depth,
-            timestamp: Clock::get()?.unix_timestamp,
+            timestamp,
         });
 
         msg!("Micro-tile settled. Recursion propagates.");
@@ -61,9 +79,21 @@ pub mod mandala_settler {
 
     /// Add liquidity (any SPL token).
     pub fn add_liquidity(ctx: Context<AddLiquidity>, amount: u64) -> Result<()> {
+        require!(
+            ctx.accounts.token_config.mode == ListingMode::Active,
+            MandalaError::TokenNotActive
+        );
+
         let vault = &mut ctx.accounts.vault;
         vault.total_liquidity = vault.total_liquidity.checked_add(amount).unwrap_or(0);
 
+        let position = &mut ctx.accounts.user_position;
+        position.vault = vault.key();
+        position.mint = ctx.accounts.token_account.mint;
+        position.owner = ctx.accounts.depositor.key();
+        position.balance = position.balance.checked_add(amount).ok_or(MandalaError::Overflow)?;
+        position.bump = ctx.bumps.user_position;
+
         emit!(LiquidityEvent { action: "add".to_string(), amount, token_mint: ctx.accounts.token_account.mint, timestamp: Clock::get()?.unix_timestamp });
         Ok(())
     }
@@ -98,10 +128,138 @@ pub mod mandala_settler {
     }
 
     /// Update vault parameters (governed by swarm).
-    pub fn update_vault_config(ctx: Context<UpdateConfig>, new_phi: u64, new_depth: u8) -> Result<()> {
+    pub fn update_vault_config(
+        ctx: Context<UpdateConfig>,
+        new_phi: u64,
+        new_depth: u8,
+        collateral_fee_rate: u64,
+    ) -> Result<()> {
         let vault = &mut ctx.accounts.vault;
         vault.phi_ratio = new_phi;
         vault.curvature_depth = new_depth;
+        vault.collateral_fee_rate = collateral_fee_rate;
+        Ok(())
+    }
+
+    /// Charge the DAO-configured collateral fee on idle liquidity (permissionless).
+    ///
+    /// `collateral_fee_rate` is an annualized bps rate, so
+    /// `fee = total_liquidity * rate_bps * elapsed / (BPS_DENOMINATOR * SECONDS_PER_YEAR)`,
+    /// computed in u128 to avoid truncation on the intermediate product.
+    pub fn charge_collateral_fees(ctx: Context<ChargeCollateralFees>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let now = Clock::get()?.unix_timestamp;
+
+        let elapsed = now
+            .checked_sub(vault.last_collateral_charge)
+            .ok_or(MandalaError::Overflow)?;
+        require!(elapsed > 0, MandalaError::AlreadyChargedThisSlot);
+
+        let fee = (vault.total_liquidity as u128)
+            .checked_mul(vault.collateral_fee_rate as u128)
+            .and_then(|v| v.checked_mul(elapsed as u128))
+            .and_then(|v| v.checked_div(BPS_DENOMINATOR))
+            .and_then(|v| v.checked_div(SECONDS_PER_YEAR))
+            .ok_or(MandalaError::Overflow)?;
+        let fee = u64::try_from(fee).map_err(|_| MandalaError::Overflow)?;
+
+        vault.last_collateral_charge = now;
+
+        if fee > 0 {
+            let seeds = &[b"mandala_vault".as_ref(), vault.authority.as_ref(), &[vault.bump]];
+            let signer = &[&seeds[..]];
+
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.vault_token_account.to_account_info(),
+                to: ctx.accounts.fee_vault.to_account_info(),
+                authority: ctx.accounts.vault.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+            token::transfer(cpi_ctx, fee)?;
+
+            vault.total_liquidity = vault
+                .total_liquidity
+                .checked_sub(fee)
+                .ok_or(MandalaError::Overflow)?;
+        }
+
+        emit!(CollateralFeeEvent {
+            fee,
+            elapsed,
+            total_liquidity: vault.total_liquidity,
+            timestamp: now,
+        });
+
+        msg!("Collateral fee accrued. Idle tiles pay their tribute.");
+        Ok(())
+    }
+
+    /// Set or update the listing mode, liquidation flag, and asset weight for a mint
+    /// (authority-gated). Lets operators list tokens without a reliable oracle as
+    /// `ReduceOnly`, then walk them down to `ForceWithdraw` for a graceful delist.
+    pub fn set_token_config(
+        ctx: Context<SetTokenConfig>,
+        mode: ListingMode,
+        disable_liquidation: bool,
+        asset_weight: u64,
+    ) -> Result<()> {
+        let token_config = &mut ctx.accounts.token_config;
+        token_config.vault = ctx.accounts.vault.key();
+        token_config.mint = ctx.accounts.mint.key();
+        token_config.mode = mode;
+        token_config.disable_liquidation = disable_liquidation;
+        token_config.asset_weight = asset_weight;
+        token_config.bump = ctx.bumps.token_config;
+
+        emit!(TokenConfigEvent {
+            mint: token_config.mint,
+            mode: token_config.mode,
+            disable_liquidation,
+            asset_weight,
+        });
+        Ok(())
+    }
+
+    /// Permissionlessly push a user's balance of a `ForceWithdraw` token back out to
+    /// them, so the mint can be fully delisted once everyone has exited.
+    pub fn force_withdraw(ctx: Context<ForceWithdraw>, amount: u64) -> Result<()> {
+        require!(
+            ctx.accounts.token_config.mode == ListingMode::ForceWithdraw,
+            MandalaError::NotForceWithdrawable
+        );
+        require!(
+            amount <= ctx.accounts.user_position.balance,
+            MandalaError::InsufficientPosition
+        );
+
+        let vault = &mut ctx.accounts.vault;
+        let seeds = &[b"mandala_vault".as_ref(), vault.authority.as_ref(), &[vault.bump]];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            to: ctx.accounts.user_token_account.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+
+        vault.total_liquidity = vault.total_liquidity.checked_sub(amount).unwrap_or(0);
+        ctx.accounts.user_position.balance = ctx
+            .accounts
+            .user_position
+            .balance
+            .checked_sub(amount)
+            .ok_or(MandalaError::Overflow)?;
+
+        emit!(LiquidityEvent {
+            action: "force_withdraw".to_string(),
+            amount,
+            token_mint: ctx.accounts.token_config.mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Force-withdrawn. Delisted tile returns to its owner.");
         Ok(())
     }
 
@@ -133,13 +291,213 @@ pub struct MandalaVault {
     pub settlement_count: u64,
     pub last_rebalance: i64,
     pub fee_vault: Pubkey,
+    pub collateral_fee_rate: u64,
+    pub last_collateral_charge: i64,
+}
+
+/// Replay-protection record for a settled payment. Derived from
+/// `["receipt", vault, hash(payment_signature)]`, so `init` itself fails the
+/// transaction if the same `payment_signature` is ever submitted twice.
+#[account]
+pub struct SettlementReceipt {
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub recipient: Pubkey,
+    pub timestamp: i64,
+    pub bump: u8,
+}
+
+/// Per-mint listing config (seeded by vault + mint), so the vault can safely list
+/// tokens that lack a reliable oracle without treating every asset as equal.
+#[account]
+pub struct TokenConfig {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    pub mode: ListingMode,
+    pub disable_liquidation: bool,
+    pub asset_weight: u64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ListingMode {
+    Active,
+    ReduceOnly,
+    ForceWithdraw,
+}
+
+/// Per-user, per-mint deposit balance (seeded by vault + mint + owner), so
+/// `force_withdraw` can bound the amount pushed out to what the user
+/// actually has on deposit instead of trusting a caller-supplied amount.
+#[account]
+pub struct UserPosition {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub balance: u64,
+    pub bump: u8,
 }
 
 // ... (rest of accounts, events — SettlementEvent now includes token_mint, EmergenceEvent added, etc.)
 
+#[derive(Accounts)]
+#[instruction(amount: u64, payment_signature: String, memo: String)]
+pub struct SettlePayment<'info> {
+    #[account(mut, seeds = [b"mandala_vault", vault.authority.as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, MandalaVault>,
+    // Must already exist for vault_token_account's mint — settling a mint with no
+    // TokenConfig at all fails here instead of silently ignoring listing mode.
+    #[account(
+        seeds = [b"token_config", vault.key().as_ref(), vault_token_account.mint.as_ref()],
+        bump = token_config.bump,
+        constraint = token_config.mint == vault_token_account.mint @ MandalaError::MintMismatch
+    )]
+    pub token_config: Account<'info, TokenConfig>,
+    #[account(mut)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+    /// CHECK: only used as the settlement's recorded recipient, never read or written.
+    pub recipient: AccountInfo<'info>,
+    // Replay guard: `init` fails the transaction outright if `payment_signature`
+    // was already used for this vault, closing the double-settle hole.
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 8 + 32 + 8 + 1,
+        seeds = [b"receipt", vault.key().as_ref(), &anchor_lang::solana_program::hash::hash(payment_signature.as_bytes()).to_bytes()],
+        bump
+    )]
+    pub receipt: Account<'info, SettlementReceipt>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddLiquidity<'info> {
+    #[account(mut, seeds = [b"mandala_vault", vault.authority.as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, MandalaVault>,
+    #[account(
+        seeds = [b"token_config", vault.key().as_ref(), token_account.mint.as_ref()],
+        bump = token_config.bump,
+        constraint = token_config.mint == token_account.mint @ MandalaError::MintMismatch
+    )]
+    pub token_config: Account<'info, TokenConfig>,
+    #[account(mut)]
+    pub token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        space = 8 + 32 + 32 + 32 + 8 + 1,
+        seeds = [b"user_position", vault.key().as_ref(), token_account.mint.as_ref(), depositor.key().as_ref()],
+        bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetTokenConfig<'info> {
+    #[account(seeds = [b"mandala_vault", vault.authority.as_ref()], bump = vault.bump, has_one = authority)]
+    pub vault: Account<'info, MandalaVault>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 32 + 1 + 1 + 8 + 1,
+        seeds = [b"token_config", vault.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub token_config: Account<'info, TokenConfig>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ForceWithdraw<'info> {
+    #[account(mut, seeds = [b"mandala_vault", vault.authority.as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, MandalaVault>,
+    #[account(seeds = [b"token_config", vault.key().as_ref(), token_config.mint.as_ref()], bump = token_config.bump)]
+    pub token_config: Account<'info, TokenConfig>,
+    #[account(mut, constraint = vault_token_account.mint == token_config.mint @ MandalaError::MintMismatch)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key() @ MandalaError::OwnerMismatch,
+        constraint = user_token_account.mint == token_config.mint @ MandalaError::MintMismatch
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"user_position", vault.key().as_ref(), token_config.mint.as_ref(), user.key().as_ref()],
+        bump = user_position.bump,
+        constraint = user_position.owner == user.key() @ MandalaError::OwnerMismatch
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    /// CHECK: owner of `user_position` and `user_token_account`; need not sign, since
+    /// funds can only go to the token account this user owns.
+    pub user: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ChargeCollateralFees<'info> {
+    #[account(mut, seeds = [b"mandala_vault", vault.authority.as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, MandalaVault>,
+    #[account(mut)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    #[account(mut, address = vault.fee_vault)]
+    pub fee_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[event]
+pub struct CollateralFeeEvent {
+    pub fee: u64,
+    pub elapsed: i64,
+    pub total_liquidity: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokenConfigEvent {
+    pub mint: Pubkey,
+    pub mode: ListingMode,
+    pub disable_liquidation: bool,
+    pub asset_weight: u64,
+}
+
 #[event]
 pub struct EmergenceEvent {
     pub recursion_depth: u8,
     pub novelty_score: u64,
     pub timestamp: i64,
 }
+
+#[error_code]
+pub enum MandalaError {
+    #[msg("Collateral fees were already charged this slot.")]
+    AlreadyChargedThisSlot,
+    #[msg("Arithmetic overflow.")]
+    Overflow,
+    #[msg("Token is not in Active listing mode.")]
+    TokenNotActive,
+    #[msg("Token is not in ForceWithdraw listing mode.")]
+    NotForceWithdrawable,
+    #[msg("Token account mint does not match the token config.")]
+    MintMismatch,
+    #[msg("Token account owner does not match the user.")]
+    OwnerMismatch,
+    #[msg("Amount exceeds the caller's tracked position balance.")]
+    InsufficientPosition,
+    #[msg("No TokenConfig exists for this mint.")]
+    MissingTokenConfig,
+}